@@ -0,0 +1,1213 @@
+//! Parses ANSI/VT escape sequences and renders them as HTML.
+use std::io::{self, Read, Write};
+
+use vte::{Params, Parser, Perform};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Intensity {
+    Bold,
+    Faint,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+enum Color {
+    N(i64),
+    RGB(i64, i64, i64),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Hyperlink {
+    uri: String,
+    id: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Underline {
+    Single,
+    Double,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct VisualState {
+    intensity: Option<Intensity>,
+    fg: Option<Color>,
+    bg: Option<Color>,
+    italic: bool,
+    underline: Option<Underline>,
+    blink: bool,
+    reverse: bool,
+    conceal: bool,
+    strikethrough: bool,
+}
+
+impl VisualState {
+    fn new() -> VisualState {
+        VisualState {
+            intensity: None,
+            fg: None,
+            bg: None,
+            italic: false,
+            underline: None,
+            blink: false,
+            reverse: false,
+            conceal: false,
+            strikethrough: false,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct Cell {
+    ch: char,
+    visual: VisualState,
+    link: Option<Hyperlink>,
+}
+
+impl Cell {
+    fn blank() -> Cell {
+        Cell {
+            ch: ' ',
+            visual: VisualState::new(),
+            link: None,
+        }
+    }
+
+    fn is_blank(&self) -> bool {
+        *self == Cell::blank()
+    }
+}
+
+/// A screen buffer: the grid of cells plus the cursor position within it.
+type Screen = (Vec<Vec<Cell>>, usize, usize);
+
+/// Controls how [`ansi_to_html`] renders the parsed screen to HTML.
+///
+/// `RenderOptions::default()` is what the `vte2html` binary uses:
+/// class-based styling, a bare fragment (no surrounding document, just the
+/// `:root` palette `<style>` block followed by the markup), the `sgr-` class
+/// prefix, and no HTML-escaping of printed text.
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    /// Emit `class="sgr-..."` for the 16 named colors and text attributes
+    /// instead of resolving everything to inline `style="..."` properties.
+    pub use_classes: bool,
+    /// Wrap the output in a standalone `<html><head>...<body>...` document
+    /// with an embedded `<style>` block defining the default `sgr-*`
+    /// classes, instead of emitting just the `:root` variables and markup.
+    pub standalone: bool,
+    /// Prefix prepended to every emitted CSS class and custom property
+    /// (`sgr-` by default, e.g. `sgr-bold` / `--sgr-fg-1`).
+    pub class_prefix: String,
+    /// HTML-escape `<`, `>` and `&` in printed characters. Off by default to
+    /// match the original binary's output; turn this on when rendering
+    /// untrusted input, since raw characters are otherwise an injection
+    /// hazard.
+    pub escape_text: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions {
+            use_classes: true,
+            standalone: false,
+            class_prefix: String::from("sgr-"),
+            escape_text: false,
+        }
+    }
+}
+
+struct Log {
+    row: usize,
+    col: usize,
+    grid: Vec<Vec<Cell>>,
+    visual_state: VisualState,
+    link: Option<Hyperlink>,
+    palette: [(u8, u8, u8); 256],
+    default_fg: (u8, u8, u8),
+    default_bg: (u8, u8, u8),
+    // Set while a primary/alternate screen (DECSET/DECRST ?1049) swap is
+    // active, holding the buffer to restore on leave.
+    alt_screen: Option<Screen>,
+    // Set between a synchronized-update DCS begin/end marker (`ESC P = 1 s`
+    // / `= 2 s`); all writes target this staged copy instead of `grid` so
+    // intervening partial frames never become visible, only the final one.
+    staging: Option<Screen>,
+    // The `1`/`2` from an in-progress `= N s` DCS, captured in `hook` and
+    // consumed in `unhook` once the (typically empty) DCS string ends.
+    dcs_sync_mode: Option<u16>,
+}
+
+impl Log {
+    fn new() -> Log {
+        Log {
+            row: 0,
+            col: 0,
+            grid: vec![Vec::new()],
+            visual_state: VisualState::new(),
+            link: None,
+            palette: default_palette(),
+            default_fg: STANDARD_COLORS[7],
+            default_bg: STANDARD_COLORS[0],
+            alt_screen: None,
+            staging: None,
+            dcs_sync_mode: None,
+        }
+    }
+}
+
+fn ensure_row(grid: &mut Vec<Vec<Cell>>, row: usize) {
+    while grid.len() <= row {
+        grid.push(Vec::new());
+    }
+}
+
+fn ensure_col(grid: &mut Vec<Vec<Cell>>, row: usize, col: usize) {
+    ensure_row(grid, row);
+    while grid[row].len() <= col {
+        grid[row].push(Cell::blank());
+    }
+}
+
+impl Log {
+    // The screen currently being written to: the staged frame while a
+    // synchronized update is in progress, otherwise the live grid.
+    fn target(&mut self) -> (&mut Vec<Vec<Cell>>, &mut usize, &mut usize) {
+        match &mut self.staging {
+            Some((grid, row, col)) => (grid, row, col),
+            None => (&mut self.grid, &mut self.row, &mut self.col),
+        }
+    }
+
+    fn write(&mut self, c: char) {
+        let visual = self.visual_state;
+        let link = self.link.clone();
+        let (grid, row, col) = self.target();
+        ensure_col(grid, *row, *col);
+        grid[*row][*col] = Cell { ch: c, visual, link };
+        *col += 1;
+    }
+
+    fn backspace(&mut self) {
+        let (_, _, col) = self.target();
+        *col = col.saturating_sub(1);
+    }
+
+    // Intentionally does not scroll: there is no configured/assumed terminal
+    // height anywhere in this crate, and this tool's purpose is converting a
+    // whole captured session into one static HTML page, so rows simply grow
+    // downward forever rather than discarding whatever scrolled off the top
+    // of a real terminal's viewport. A full-screen program that free-scrolls
+    // via plain line feeds (rather than `?1049h` alt-screen or explicit
+    // cursor addressing) will therefore render as one long page instead of
+    // a fixed-height window — out of scope unless this crate grows a real
+    // `rows`/`cols` concept.
+    fn line_feed(&mut self) {
+        let (grid, row, _) = self.target();
+        *row += 1;
+        ensure_row(grid, *row);
+    }
+
+    fn carriage_return(&mut self) {
+        let (_, _, col) = self.target();
+        *col = 0;
+    }
+
+    fn cursor_up(&mut self, n: usize) {
+        let (_, row, _) = self.target();
+        *row = row.saturating_sub(n);
+    }
+
+    fn cursor_down(&mut self, n: usize) {
+        let (grid, row, _) = self.target();
+        *row += n;
+        ensure_row(grid, *row);
+    }
+
+    fn cursor_forward(&mut self, n: usize) {
+        let (_, _, col) = self.target();
+        *col += n;
+    }
+
+    fn cursor_back(&mut self, n: usize) {
+        let (_, _, col) = self.target();
+        *col = col.saturating_sub(n);
+    }
+
+    // row/col are 1-based, as they arrive in CUP/HVP params.
+    fn cursor_position(&mut self, row: usize, col: usize) {
+        let (grid, r, c) = self.target();
+        *r = row.saturating_sub(1);
+        *c = col.saturating_sub(1);
+        ensure_row(grid, *r);
+    }
+
+    fn erase_line(&mut self, mode: u16) {
+        let (grid, row, col) = self.target();
+        ensure_row(grid, *row);
+        let len = grid[*row].len();
+        match mode {
+            0 => {
+                let start = (*col).min(len);
+                for cell in &mut grid[*row][start..] {
+                    *cell = Cell::blank();
+                }
+            }
+            1 if len > 0 => {
+                let end = (*col).min(len - 1);
+                for cell in &mut grid[*row][..=end] {
+                    *cell = Cell::blank();
+                }
+            }
+            1 => (),
+            2 => grid[*row].clear(),
+            _ => (),
+        }
+    }
+
+    fn erase_display(&mut self, mode: u16) {
+        match mode {
+            0 => {
+                self.erase_line(0);
+                let (grid, row, _) = self.target();
+                let row = *row;
+                for r in &mut grid[row + 1..] {
+                    r.clear();
+                }
+            }
+            1 => {
+                self.erase_line(1);
+                let (grid, row, _) = self.target();
+                let row = *row;
+                for r in &mut grid[..row] {
+                    r.clear();
+                }
+            }
+            2 => {
+                let (grid, _, _) = self.target();
+                for r in grid.iter_mut() {
+                    r.clear();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn set_private_mode(&mut self, mode: Option<u16>, enabled: bool) {
+        if mode != Some(1049) {
+            return;
+        }
+        if enabled {
+            let backup = (std::mem::replace(&mut self.grid, vec![Vec::new()]), self.row, self.col);
+            self.alt_screen = Some(backup);
+            self.row = 0;
+            self.col = 0;
+        } else if let Some((grid, row, col)) = self.alt_screen.take() {
+            self.grid = grid;
+            self.row = row;
+            self.col = col;
+        }
+    }
+
+    fn begin_dcs(&mut self, params: &Params, intermediates: &[u8], c: char) {
+        self.dcs_sync_mode = (matches!(intermediates, [b'=']) && c == 's')
+            .then(|| nth_param(params, 0))
+            .flatten();
+    }
+
+    fn end_dcs(&mut self) {
+        match self.dcs_sync_mode.take() {
+            Some(1) => self.staging = Some((self.grid.clone(), self.row, self.col)),
+            Some(2) => {
+                if let Some((grid, row, col)) = self.staging.take() {
+                    self.grid = grid;
+                    self.row = row;
+                    self.col = col;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn set_intensity(&mut self, intensity: Option<Intensity>) {
+        self.visual_state = VisualState {
+            intensity,
+            ..self.visual_state
+        }
+    }
+    fn set_fg(&mut self, fg: Option<Color>) {
+        self.visual_state = VisualState {
+            fg,
+            ..self.visual_state
+        }
+    }
+    fn set_bg(&mut self, bg: Option<Color>) {
+        self.visual_state = VisualState {
+            bg,
+            ..self.visual_state
+        }
+    }
+    fn set_link(&mut self, link: Option<Hyperlink>) {
+        self.link = link;
+    }
+    fn set_palette_entry(&mut self, index: usize, rgb: (u8, u8, u8)) {
+        if index < self.palette.len() {
+            self.palette[index] = rgb;
+        }
+    }
+    fn set_default_fg(&mut self, rgb: (u8, u8, u8)) {
+        self.default_fg = rgb;
+    }
+    fn set_default_bg(&mut self, rgb: (u8, u8, u8)) {
+        self.default_bg = rgb;
+    }
+    fn set_italic(&mut self, italic: bool) {
+        self.visual_state = VisualState {
+            italic,
+            ..self.visual_state
+        }
+    }
+    fn set_underline(&mut self, underline: Option<Underline>) {
+        self.visual_state = VisualState {
+            underline,
+            ..self.visual_state
+        }
+    }
+    fn set_blink(&mut self, blink: bool) {
+        self.visual_state = VisualState {
+            blink,
+            ..self.visual_state
+        }
+    }
+    fn set_reverse(&mut self, reverse: bool) {
+        self.visual_state = VisualState {
+            reverse,
+            ..self.visual_state
+        }
+    }
+    fn set_conceal(&mut self, conceal: bool) {
+        self.visual_state = VisualState {
+            conceal,
+            ..self.visual_state
+        }
+    }
+    fn set_strikethrough(&mut self, strikethrough: bool) {
+        self.visual_state = VisualState {
+            strikethrough,
+            ..self.visual_state
+        }
+    }
+}
+
+impl Perform for Log {
+    fn print(&mut self, c: char) {
+        self.write(c);
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            0x0a => self.line_feed(),
+            0x0d => self.carriage_return(),
+            0x08 => self.backspace(),
+            _ => (),
+        }
+    }
+
+    fn hook(&mut self, params: &Params, intermediates: &[u8], _ignore: bool, c: char) {
+        self.begin_dcs(params, intermediates, c);
+    }
+
+    fn put(&mut self, _byte: u8) {}
+
+    fn unhook(&mut self) {
+        self.end_dcs();
+    }
+
+    fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+        match params.first().copied() {
+            Some(b"8") => {
+                // The URI itself may legally contain `;`, but `vte` splits the
+                // whole OSC payload on every `;`, so it arrives as however
+                // many params follow the `id=` one; rejoin them.
+                let uri_parts: &[&[u8]] = if params.len() > 2 { &params[2..] } else { &[] };
+                if uri_parts.iter().all(|p| p.is_empty()) {
+                    self.set_link(None);
+                    return;
+                }
+                let uri = uri_parts
+                    .iter()
+                    .map(|p| String::from_utf8_lossy(p))
+                    .collect::<Vec<_>>()
+                    .join(";");
+                let id = params.get(1).and_then(|p| {
+                    std::str::from_utf8(p)
+                        .ok()
+                        .and_then(|p| p.strip_prefix("id="))
+                        .map(String::from)
+                });
+                self.set_link(Some(Hyperlink { uri, id }));
+            }
+            Some(b"4") => {
+                // `4;index1;spec1;index2;spec2;...` - any number of pairs.
+                for chunk in params[1..].chunks_exact(2) {
+                    let index = std::str::from_utf8(chunk[0])
+                        .ok()
+                        .and_then(|s| s.parse::<usize>().ok());
+                    let rgb = std::str::from_utf8(chunk[1]).ok().and_then(xparse_color);
+                    if let (Some(index), Some(rgb)) = (index, rgb) {
+                        self.set_palette_entry(index, rgb);
+                    }
+                }
+            }
+            Some(b"10") => {
+                if let Some(rgb) = params
+                    .get(1)
+                    .and_then(|p| std::str::from_utf8(p).ok())
+                    .and_then(xparse_color)
+                {
+                    self.set_default_fg(rgb);
+                }
+            }
+            Some(b"11") => {
+                if let Some(rgb) = params
+                    .get(1)
+                    .and_then(|p| std::str::from_utf8(p).ok())
+                    .and_then(xparse_color)
+                {
+                    self.set_default_bg(rgb);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn csi_dispatch(&mut self, params: &Params, intermediates: &[u8], _ignore: bool, c: char) {
+        match c {
+            // visual style commands
+            'm' => {
+                let mut iter = params.iter();
+                while let Some(group) = iter.next() {
+                    let p = group[0];
+                    match p {
+                        0 => self.visual_state = VisualState::new(),
+                        1 => self.set_intensity(Some(Intensity::Bold)),
+                        2 => self.set_intensity(Some(Intensity::Faint)),
+                        3 => self.set_italic(true),
+                        4 => self.set_underline(Some(Underline::Single)),
+                        5 | 6 => self.set_blink(true),
+                        7 => self.set_reverse(true),
+                        8 => self.set_conceal(true),
+                        9 => self.set_strikethrough(true),
+                        21 => self.set_underline(Some(Underline::Double)),
+                        22 => self.set_intensity(None),
+                        23 => self.set_italic(false),
+                        24 => self.set_underline(None),
+                        25 => self.set_blink(false),
+                        27 => self.set_reverse(false),
+                        28 => self.set_conceal(false),
+                        29 => self.set_strikethrough(false),
+                        30..=37 => self.set_fg(Some(Color::N((p - 30) as i64))),
+                        38 => {
+                            if let Some(color) = parse_extended_color(group, &mut iter) {
+                                self.set_fg(Some(color));
+                            }
+                        }
+                        39 => self.set_fg(None),
+                        40..=47 => self.set_bg(Some(Color::N((p - 40) as i64))),
+                        48 => {
+                            if let Some(color) = parse_extended_color(group, &mut iter) {
+                                self.set_bg(Some(color));
+                            }
+                        }
+                        49 => self.set_bg(None),
+                        90..=97 => self.set_fg(Some(Color::N((p - 90 + 8) as i64))),
+                        100..=107 => self.set_bg(Some(Color::N((p - 100 + 8) as i64))),
+                        _ => (),
+                    }
+                }
+            }
+            'K' => self.erase_line(erase_mode(params, 0)),
+            'J' => self.erase_display(erase_mode(params, 0)),
+            'A' => self.cursor_up(movement_count(params, 0)),
+            'B' => self.cursor_down(movement_count(params, 0)),
+            'C' => self.cursor_forward(movement_count(params, 0)),
+            'D' => self.cursor_back(movement_count(params, 0)),
+            'H' | 'f' => {
+                self.cursor_position(movement_count(params, 0), movement_count(params, 1))
+            }
+            'h' if matches!(intermediates, [b'?']) => {
+                self.set_private_mode(nth_param(params, 0), true)
+            }
+            'l' if matches!(intermediates, [b'?']) => {
+                self.set_private_mode(nth_param(params, 0), false)
+            }
+            _ => (),
+        }
+    }
+
+    fn esc_dispatch(&mut self, _intermediates: &[u8], _ignore: bool, _byte: u8) {}
+}
+
+/// Reads the first value of the `index`-th CSI parameter, if present.
+fn nth_param(params: &Params, index: usize) -> Option<u16> {
+    params.iter().nth(index).and_then(|group| group.first()).copied()
+}
+
+/// Cursor-movement counts (CUU/CUD/CUF/CUB/CUP) default to 1 when the
+/// parameter is missing or explicitly 0, per ECMA-48.
+fn movement_count(params: &Params, index: usize) -> usize {
+    match nth_param(params, index) {
+        None | Some(0) => 1,
+        Some(n) => n as usize,
+    }
+}
+
+/// Erase commands (ED/EL) default their mode to 0 when the parameter is
+/// missing, with 0 itself a meaningful explicit mode.
+fn erase_mode(params: &Params, index: usize) -> u16 {
+    nth_param(params, index).unwrap_or(0)
+}
+
+/// Resolves an SGR 38/48 extended color selector. `group` is the params slice
+/// starting at the `38`/`48` entry itself; `rest` yields any following
+/// semicolon-separated params, which is how non-colon-subparam sequences
+/// (`38;5;n` / `38;2;r;g;b`) deliver their arguments.
+fn parse_extended_color<'a>(
+    group: &[u16],
+    rest: &mut impl Iterator<Item = &'a [u16]>,
+) -> Option<Color> {
+    if group.len() > 1 {
+        return color_from_mode(&group[1..]);
+    }
+    let mode = *rest.next()?.first()?;
+    match mode {
+        5 => {
+            let n = *rest.next()?.first()?;
+            Some(Color::N(n as i64))
+        }
+        2 => {
+            let r = *rest.next()?.first()?;
+            let g = *rest.next()?.first()?;
+            let b = *rest.next()?.first()?;
+            Some(Color::RGB(r as i64, g as i64, b as i64))
+        }
+        _ => None,
+    }
+}
+
+/// Same as `parse_extended_color` but for the case where `5;n` or `2;r;g;b`
+/// (optionally with a leading colorspace id before the RGB triplet) arrived
+/// as colon-separated subparams of the `38`/`48` entry itself.
+fn color_from_mode(args: &[u16]) -> Option<Color> {
+    match *args.first()? {
+        5 => args.get(1).map(|n| Color::N(*n as i64)),
+        2 if args.len() >= 4 => {
+            let rgb = &args[args.len() - 3..];
+            Some(Color::RGB(rgb[0] as i64, rgb[1] as i64, rgb[2] as i64))
+        }
+        _ => None,
+    }
+}
+
+/// Converts an extended 256-color palette index (16-231 color cube, 232-255
+/// grayscale ramp) to its 8-bit RGB components, per the standard xterm
+/// palette layout.
+fn indexed_to_rgb(index: i64) -> (u8, u8, u8) {
+    match index {
+        16..=231 => {
+            let i = index - 16;
+            let component = |v: i64| if v == 0 { 0 } else { (55 + 40 * v) as u8 };
+            (component(i / 36), component((i / 6) % 6), component(i % 6))
+        }
+        232..=255 => {
+            let v = (8 + 10 * (index - 232)) as u8;
+            (v, v, v)
+        }
+        other => panic!("Unexpected extended color index {}", other),
+    }
+}
+
+/// The standard 16-entry xterm ANSI palette, used as the default before any
+/// OSC 4 redefinitions arrive.
+const STANDARD_COLORS: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+fn default_palette() -> [(u8, u8, u8); 256] {
+    let mut palette = [(0u8, 0u8, 0u8); 256];
+    palette[0..16].copy_from_slice(&STANDARD_COLORS);
+    for (i, entry) in palette.iter_mut().enumerate().skip(16) {
+        *entry = indexed_to_rgb(i as i64);
+    }
+    palette
+}
+
+/// The CSS custom-property suffix for a 16-color palette slot, matching the
+/// `sgr-fg-N`/`sgr-fg-bN` class naming used when rendering.
+fn color_slot_name(num: i64) -> String {
+    match num {
+        0..=7 => format!("{}", num + 1),
+        8..=15 => format!("b{}", num - 7),
+        other => panic!("Unexpected palette slot index {}", other),
+    }
+}
+
+/// Parses an XParseColor-style color spec as used by OSC 4/10/11:
+/// `rgb:RRRR/GGGG/BBBB` (1-4 hex digits per channel) or the legacy
+/// `#RGB`/`#RRGGBB`/`#RRRRGGGGBBBB` forms.
+fn xparse_color(spec: &str) -> Option<(u8, u8, u8)> {
+    if let Some(rgb) = spec.strip_prefix("rgb:") {
+        let mut channels = rgb.split('/');
+        let r = parse_color_channel(channels.next()?)?;
+        let g = parse_color_channel(channels.next()?)?;
+        let b = parse_color_channel(channels.next()?)?;
+        return if channels.next().is_none() {
+            Some((r, g, b))
+        } else {
+            None
+        };
+    }
+    if let Some(hex) = spec.strip_prefix('#') {
+        if hex.len() % 3 != 0 {
+            return None;
+        }
+        let chunk = hex.len() / 3;
+        let r = parse_color_channel(&hex[0..chunk])?;
+        let g = parse_color_channel(&hex[chunk..2 * chunk])?;
+        let b = parse_color_channel(&hex[2 * chunk..3 * chunk])?;
+        return Some((r, g, b));
+    }
+    None
+}
+
+fn parse_color_channel(s: &str) -> Option<u8> {
+    if s.is_empty() || s.len() > 4 || !s.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let value = u32::from_str_radix(s, 16).ok()?;
+    let max = 16u32.pow(s.len() as u32) - 1;
+    Some((255 * value / max) as u8)
+}
+
+/// Renders a `:root { --<prefix>fg-N: #rrggbb; ... }` style block reflecting
+/// the runtime palette (as redefined via OSC 4/10/11), so the class-based
+/// spans pick up the actual colors instead of assuming a fixed stylesheet.
+fn render_palette_style(
+    palette: &[(u8, u8, u8); 256],
+    default_fg: (u8, u8, u8),
+    default_bg: (u8, u8, u8),
+    prefix: &str,
+) -> String {
+    let mut vars = String::new();
+    for num in 0..16 {
+        let (r, g, b) = palette[num as usize];
+        let slot = color_slot_name(num);
+        vars.push_str(&format!("--{}fg-{}:#{:02x}{:02x}{:02x};", prefix, slot, r, g, b));
+        vars.push_str(&format!("--{}bg-{}:#{:02x}{:02x}{:02x};", prefix, slot, r, g, b));
+    }
+    let (fr, fg, fb) = default_fg;
+    let (br, bgg, bb) = default_bg;
+    vars.push_str(&format!("--{}default-fg:#{:02x}{:02x}{:02x};", prefix, fr, fg, fb));
+    vars.push_str(&format!("--{}default-bg:#{:02x}{:02x}{:02x};", prefix, br, bgg, bb));
+    format!("<style>:root{{{}}}</style>", vars)
+}
+
+/// The default rule set for the `sgr-*` classes `print_span` emits, for use
+/// in `RenderOptions::standalone` documents that aren't paired with an
+/// external stylesheet. Color classes read from the `:root` variables that
+/// `render_palette_style` defines.
+fn default_stylesheet(prefix: &str) -> String {
+    let mut css = String::new();
+    css.push_str(&format!(
+        "body{{color:var(--{prefix}default-fg);background-color:var(--{prefix}default-bg);\
+         white-space:pre;font-family:monospace}}",
+        prefix = prefix
+    ));
+    css.push_str(&format!(".{}bold{{font-weight:bold}}", prefix));
+    css.push_str(&format!(".{}faint{{opacity:0.67}}", prefix));
+    css.push_str(&format!(".{}italic{{font-style:italic}}", prefix));
+    css.push_str(&format!(".{}underline{{text-decoration:underline}}", prefix));
+    css.push_str(&format!(
+        ".{}underline-double{{text-decoration:underline;text-decoration-style:double}}",
+        prefix
+    ));
+    css.push_str(&format!(".{}blink{{text-decoration:blink}}", prefix));
+    css.push_str(&format!(".{}conceal{{visibility:hidden}}", prefix));
+    css.push_str(&format!(
+        ".{}strikethrough{{text-decoration:line-through}}",
+        prefix
+    ));
+    for num in 0..16 {
+        let slot = color_slot_name(num);
+        css.push_str(&format!(
+            ".{prefix}fg-{slot}{{color:var(--{prefix}fg-{slot})}}",
+            prefix = prefix,
+            slot = slot
+        ));
+        css.push_str(&format!(
+            ".{prefix}bg-{slot}{{background-color:var(--{prefix}bg-{slot})}}",
+            prefix = prefix,
+            slot = slot
+        ));
+    }
+    css
+}
+
+fn escape_html_attribute(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn write_text_char(out: &mut impl Write, c: char, opts: &RenderOptions) -> io::Result<()> {
+    match c {
+        '&' if opts.escape_text => write!(out, "&amp;"),
+        '<' if opts.escape_text => write!(out, "&lt;"),
+        '>' if opts.escape_text => write!(out, "&gt;"),
+        other => write!(out, "{}", other),
+    }
+}
+
+/// Pushes the text-attribute part of a cell's visual state (intensity,
+/// italic, underline, blink, conceal, strikethrough) as either CSS classes
+/// or inline style properties, depending on `opts.use_classes`.
+fn push_attribute_rules(
+    visual: &VisualState,
+    opts: &RenderOptions,
+    classes: &mut Vec<String>,
+    styles: &mut Vec<String>,
+) {
+    if opts.use_classes {
+        match visual.intensity {
+            Some(Intensity::Bold) => classes.push(format!("{}bold", opts.class_prefix)),
+            Some(Intensity::Faint) => classes.push(format!("{}faint", opts.class_prefix)),
+            None => (),
+        }
+        if visual.italic {
+            classes.push(format!("{}italic", opts.class_prefix));
+        }
+        match visual.underline {
+            Some(Underline::Single) => classes.push(format!("{}underline", opts.class_prefix)),
+            Some(Underline::Double) => {
+                classes.push(format!("{}underline-double", opts.class_prefix))
+            }
+            None => (),
+        }
+        if visual.blink {
+            classes.push(format!("{}blink", opts.class_prefix));
+        }
+        if visual.conceal {
+            classes.push(format!("{}conceal", opts.class_prefix));
+        }
+        if visual.strikethrough {
+            classes.push(format!("{}strikethrough", opts.class_prefix));
+        }
+        return;
+    }
+
+    match visual.intensity {
+        Some(Intensity::Bold) => styles.push(String::from("font-weight:bold")),
+        Some(Intensity::Faint) => styles.push(String::from("opacity:0.67")),
+        None => (),
+    }
+    if visual.italic {
+        styles.push(String::from("font-style:italic"));
+    }
+    let mut decorations: Vec<&str> = Vec::new();
+    if visual.underline.is_some() {
+        decorations.push("underline");
+    }
+    if visual.strikethrough {
+        decorations.push("line-through");
+    }
+    if visual.blink {
+        decorations.push("blink");
+    }
+    if !decorations.is_empty() {
+        styles.push(format!("text-decoration:{}", decorations.join(" ")));
+    }
+    if visual.underline == Some(Underline::Double) {
+        styles.push(String::from("text-decoration-style:double"));
+    }
+    if visual.conceal {
+        styles.push(String::from("visibility:hidden"));
+    }
+}
+
+fn rgb_color((r, g, b): (u8, u8, u8)) -> Color {
+    Color::RGB(r as i64, g as i64, b as i64)
+}
+
+/// Pushes a resolved fg/bg color as either a CSS class (for the 16 named
+/// colors, when `opts.use_classes`) or an inline style property.
+fn push_color_rule(
+    color: Option<Color>,
+    is_fg: bool,
+    opts: &RenderOptions,
+    palette: &[(u8, u8, u8); 256],
+    classes: &mut Vec<String>,
+    styles: &mut Vec<String>,
+) {
+    let color = match color {
+        Some(color) => color,
+        None => return,
+    };
+    let prop = if is_fg { "color" } else { "background-color" };
+    match color {
+        Color::N(num) if (0..=15).contains(&num) && opts.use_classes => {
+            let kind = if is_fg { "fg" } else { "bg" };
+            classes.push(format!(
+                "{}{}-{}",
+                opts.class_prefix,
+                kind,
+                color_slot_name(num)
+            ));
+        }
+        Color::N(num) if (0..=15).contains(&num) => {
+            let (r, g, b) = palette[num as usize];
+            styles.push(format!("{}:#{:02x}{:02x}{:02x}", prop, r, g, b));
+        }
+        Color::N(num) if (16..=255).contains(&num) => {
+            let (r, g, b) = palette[num as usize];
+            styles.push(format!("{}:#{:02x}{:02x}{:02x}", prop, r, g, b));
+        }
+        // An out-of-range index (e.g. from a malformed `38;5;999` sequence)
+        // has no corresponding color; rather than aborting the whole
+        // conversion, just leave this cell unstyled.
+        Color::N(_) => (),
+        Color::RGB(r, g, b) => {
+            styles.push(format!("{}:#{:02x}{:02x}{:02x}", prop, r, g, b));
+        }
+    }
+}
+
+fn write_span_open(
+    out: &mut impl Write,
+    visual: VisualState,
+    opts: &RenderOptions,
+    palette: &[(u8, u8, u8); 256],
+    default_fg: (u8, u8, u8),
+    default_bg: (u8, u8, u8),
+) -> io::Result<bool> {
+    let mut classes: Vec<String> = Vec::new();
+    let mut styles: Vec<String> = Vec::new();
+
+    push_attribute_rules(&visual, opts, &mut classes, &mut styles);
+
+    // Reverse video swaps the effective fg/bg at render time (rather than
+    // storing them swapped) so that a later plain 39/49 reset still clears
+    // the right slot. A cell with no explicit color still has a visible
+    // default fg/bg, so fall back to those before swapping.
+    let (fg, bg) = if visual.reverse {
+        let fg = visual.fg.unwrap_or_else(|| rgb_color(default_fg));
+        let bg = visual.bg.unwrap_or_else(|| rgb_color(default_bg));
+        (Some(bg), Some(fg))
+    } else {
+        (visual.fg, visual.bg)
+    };
+    push_color_rule(fg, true, opts, palette, &mut classes, &mut styles);
+    push_color_rule(bg, false, opts, palette, &mut classes, &mut styles);
+
+    let span_printed = !classes.is_empty() || !styles.is_empty();
+    if span_printed {
+        write!(out, "<span")?;
+        if !classes.is_empty() {
+            write!(out, " class=\"{}\"", classes.join(" "))?;
+        }
+        if !styles.is_empty() {
+            write!(out, " style=\"{}\"", styles.join(";"))?;
+        }
+        write!(out, ">")?;
+    }
+    Ok(span_printed)
+}
+
+fn render(performer: &Log, opts: &RenderOptions, out: &mut impl Write) -> io::Result<()> {
+    if opts.standalone {
+        write!(
+            out,
+            "<html><head><style>{}</style>{}</head><body>",
+            default_stylesheet(&opts.class_prefix),
+            render_palette_style(
+                &performer.palette,
+                performer.default_fg,
+                performer.default_bg,
+                &opts.class_prefix
+            ),
+        )?;
+    } else {
+        write!(
+            out,
+            "{}",
+            render_palette_style(
+                &performer.palette,
+                performer.default_fg,
+                performer.default_bg,
+                &opts.class_prefix
+            )
+        )?;
+    }
+
+    let mut previous_had_span = false;
+    let mut link_open = false;
+    let mut previous: Option<&Cell> = None;
+    let row_count = performer.grid.len();
+
+    for (r, row) in performer.grid.iter().enumerate() {
+        let visible_len = row
+            .iter()
+            .rposition(|cell| !cell.is_blank())
+            .map_or(0, |i| i + 1);
+
+        for cell in &row[..visible_len] {
+            let link_changed = previous.is_none_or(|prev| prev.link != cell.link);
+            let visual_changed = previous.is_none_or(|prev| prev.visual != cell.visual);
+
+            if link_changed || visual_changed {
+                if previous_had_span {
+                    write!(out, "</span>")?;
+                }
+                if link_changed {
+                    if link_open {
+                        write!(out, "</a>")?;
+                    }
+                    link_open = cell.link.is_some();
+                    if let Some(link) = &cell.link {
+                        write!(out, "<a href=\"{}\">", escape_html_attribute(&link.uri))?;
+                    }
+                }
+                previous_had_span = write_span_open(
+                    out,
+                    cell.visual,
+                    opts,
+                    &performer.palette,
+                    performer.default_fg,
+                    performer.default_bg,
+                )?;
+            }
+
+            write_text_char(out, cell.ch, opts)?;
+            previous = Some(cell);
+        }
+
+        if r + 1 < row_count {
+            writeln!(out)?;
+        }
+    }
+
+    if previous_had_span {
+        write!(out, "</span>")?;
+    }
+    if link_open {
+        write!(out, "</a>")?;
+    }
+
+    if opts.standalone {
+        write!(out, "</body></html>")?;
+    }
+
+    Ok(())
+}
+
+/// Parses ANSI/VT escape sequences from `input` and writes the equivalent
+/// HTML to `out`, per `opts`. This is the library entry point; the
+/// `vte2html` binary is a thin wrapper around it using
+/// `RenderOptions::default()`.
+pub fn ansi_to_html(mut input: impl Read, mut out: impl Write, opts: RenderOptions) -> io::Result<()> {
+    let mut statemachine = Parser::new();
+    let mut performer = Log::new();
+
+    let mut buf = [0; 2048];
+    loop {
+        match input.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                for byte in &buf[..n] {
+                    statemachine.advance(&mut performer, *byte);
+                }
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    render(&performer, &opts, &mut out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_to_string(input: &[u8], opts: RenderOptions) -> String {
+        let mut out = Vec::new();
+        ansi_to_html(input, &mut out, opts).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn basic_sgr_bold_and_color() {
+        let html = render_to_string(b"\x1b[1;31mhi\x1b[0m", RenderOptions::default());
+        assert!(html.contains("sgr-bold"));
+        assert!(html.contains("sgr-fg-2"));
+        assert!(html.contains("hi"));
+    }
+
+    #[test]
+    fn extended_256_color_reads_redefined_palette_entry() {
+        let input = b"\x1b]4;200;rgb:ffff/0000/0000\x1b\\\x1b[38;5;200mx\x1b[0m";
+        let html = render_to_string(input, RenderOptions::default());
+        assert!(html.contains("color:#ff0000"));
+    }
+
+    #[test]
+    fn extended_color_out_of_range_does_not_panic() {
+        let html = render_to_string(b"\x1b[38;5;999mx\x1b[0m", RenderOptions::default());
+        assert!(html.contains('x'));
+    }
+
+    #[test]
+    fn osc_8_hyperlink_renders_anchor() {
+        let input = b"\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x1b\\";
+        let html = render_to_string(input, RenderOptions::default());
+        assert!(html.contains("<a href=\"https://example.com\">"));
+        assert!(html.contains("</a>"));
+    }
+
+    #[test]
+    fn osc_8_hyperlink_preserves_semicolons_in_the_uri() {
+        let input = b"\x1b]8;;https://example.com/a;b=2\x1b\\link\x1b]8;;\x1b\\";
+        let html = render_to_string(input, RenderOptions::default());
+        assert!(html.contains("<a href=\"https://example.com/a;b=2\">"));
+    }
+
+    #[test]
+    fn reverse_video_falls_back_to_default_colors() {
+        let html = render_to_string(b"\x1b[7mx\x1b[0m", RenderOptions::default());
+        assert!(html.contains("color:#"));
+        assert!(html.contains("background-color:#"));
+    }
+
+    #[test]
+    fn cursor_position_addresses_a_later_row_and_column() {
+        let input = b"a\x1b[3;3Hb";
+        let html = render_to_string(input, RenderOptions::default());
+        let lines: Vec<&str> = html.split('\n').collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[2].ends_with("b"));
+    }
+
+    #[test]
+    fn alt_screen_discards_its_contents_on_leave() {
+        // Write "ALPHA" on the primary screen, enter the alt screen and
+        // write "BETA", then leave; only "ALPHA" should survive into the
+        // rendered output.
+        let input = b"ALPHA\x1b[?1049hBETA\x1b[?1049l";
+        let html = render_to_string(input, RenderOptions::default());
+        assert!(html.contains("ALPHA"));
+        assert!(!html.contains("BETA"));
+    }
+
+    #[test]
+    fn sync_update_hides_writes_until_the_end_marker_commits() {
+        // Begin a synchronized update (`=1s`), write "BETA", but never send
+        // the `=2s` end marker: "BETA" must not reach the rendered grid.
+        let input = b"ALPHA\x1bP=1s\x1b\\BETA";
+        let html = render_to_string(input, RenderOptions::default());
+        assert!(html.contains("ALPHA"));
+        assert!(!html.contains("BETA"));
+    }
+
+    #[test]
+    fn sync_update_commits_the_staged_frame_on_end_marker() {
+        let input = b"ALPHA\x1bP=1s\x1b\\BETA\x1bP=2s\x1b\\";
+        let html = render_to_string(input, RenderOptions::default());
+        assert!(html.contains("ALPHABETA"));
+    }
+
+    #[test]
+    fn erase_display_mode_2_clears_the_whole_grid() {
+        let input = b"ALPHA\nBETA\x1b[2J";
+        let html = render_to_string(input, RenderOptions::default());
+        assert!(!html.contains("ALPHA"));
+        assert!(!html.contains("BETA"));
+    }
+
+    #[test]
+    fn erase_line_mode_1_clears_from_start_through_the_cursor() {
+        // Write "ALPHABETA", move the cursor to the 'B' (0-based column 5),
+        // then erase from the start of the line through the cursor: only
+        // "ETA" should remain.
+        let input = b"ALPHABETA\x1b[1;6H\x1b[1K";
+        let html = render_to_string(input, RenderOptions::default());
+        assert!(!html.contains("ALPHA"));
+        assert!(html.contains("ETA"));
+    }
+
+    #[test]
+    fn standalone_option_wraps_output_in_a_document() {
+        let html = render_to_string(b"hi", standalone_opts());
+        assert!(html.starts_with("<html>"));
+        assert!(html.contains("<body>"));
+        assert!(html.ends_with("</html>"));
+    }
+
+    #[test]
+    fn use_classes_false_renders_inline_styles_instead_of_classes() {
+        let opts = RenderOptions {
+            use_classes: false,
+            ..RenderOptions::default()
+        };
+        let html = render_to_string(b"\x1b[1mhi\x1b[0m", opts);
+        assert!(html.contains("font-weight:bold"));
+        assert!(!html.contains("sgr-bold"));
+    }
+
+    #[test]
+    fn escape_text_true_escapes_angle_brackets_and_ampersand() {
+        let opts = RenderOptions {
+            escape_text: true,
+            ..RenderOptions::default()
+        };
+        let html = render_to_string(b"<b>&</b>", opts);
+        assert!(html.contains("&lt;b&gt;&amp;&lt;/b&gt;"));
+    }
+
+    #[test]
+    fn escape_text_false_leaves_text_raw_by_default() {
+        let html = render_to_string(b"<b>", RenderOptions::default());
+        assert!(html.contains("<b>"));
+    }
+
+    #[test]
+    fn custom_class_prefix_is_used_instead_of_sgr() {
+        let opts = RenderOptions {
+            class_prefix: String::from("termhtml-"),
+            ..RenderOptions::default()
+        };
+        let html = render_to_string(b"\x1b[1mhi\x1b[0m", opts);
+        assert!(html.contains("termhtml-bold"));
+        assert!(!html.contains("sgr-bold"));
+    }
+
+    fn standalone_opts() -> RenderOptions {
+        RenderOptions {
+            standalone: true,
+            ..RenderOptions::default()
+        }
+    }
+}